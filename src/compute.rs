@@ -0,0 +1,258 @@
+//! `compute` is an optional OpenCL-backed acceleration layer for per-pixel operations on
+//! `RgbImg`. It exists behind the `compute` feature flag so the default build stays
+//! dependency-free; callers that enable the feature get `RgbImg::greyscale_gpu` and the
+//! lower-level [`Compute::greyscale`] primitive it's built on.
+//!
+//! Because `RgbImg` already stores `r`, `g`, and `b` as 3 separate contiguous buffers, each
+//! channel uploads to its own device buffer with no interleave/deinterleave step. Where the
+//! platform supports shared virtual memory (SVM), buffers are allocated so the device can read
+//! and write them directly, which spares the kernel launch itself an explicit host-to-device
+//! transfer; otherwise this falls back to explicit enqueue-write/enqueue-read calls. Either way,
+//! the result handed back to the caller is a freshly-allocated `MiniVec`, since that's the
+//! buffer type the rest of this crate's public API deals in.
+
+use opencl3::command_queue::CommandQueue;
+use opencl3::context::Context;
+use opencl3::device::{Device as ClDevice, CL_DEVICE_TYPE_GPU};
+use opencl3::kernel::{ExecuteKernel, Kernel};
+use opencl3::memory::{Buffer, CL_MEM_READ_ONLY, CL_MEM_WRITE_ONLY};
+use opencl3::platform::get_platforms;
+use opencl3::program::Program;
+use opencl3::svm::SvmVec;
+use opencl3::types::{cl_uchar, CL_BLOCKING};
+
+const GREYSCALE_KERNEL_SRC: &str = r#"
+__kernel void greyscale(__global const uchar *r,
+                         __global const uchar *g,
+                         __global const uchar *b,
+                         __global uchar *out) {
+    size_t i = get_global_id(0);
+
+    float grey = 0.21263901f * ((float)r[i] / 255.0f)
+               + 0.71516868f * ((float)g[i] / 255.0f)
+               + 0.07219232f * ((float)b[i] / 255.0f);
+
+    out[i] = (uchar)(min(grey, 1.0f) * 255.0f);
+}
+"#;
+
+/// `ComputeError` enumerates everything that can go wrong talking to an OpenCL device, from
+/// platform discovery through kernel execution.
+///
+#[derive(Debug)]
+pub enum ComputeError {
+    /// No OpenCL platform is installed on this machine.
+    NoPlatform,
+    /// A platform was found, but it exposes no usable GPU device.
+    NoDevice,
+    /// The device or command queue could not be created.
+    ContextCreationFailed,
+    /// The kernel source failed to compile for the selected device.
+    KernelBuildFailed(String),
+    /// Enqueuing a buffer transfer or kernel launch failed.
+    Enqueue(String),
+}
+
+impl std::fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputeError::NoPlatform => write!(f, "no OpenCL platform is available"),
+            ComputeError::NoDevice => write!(f, "no usable OpenCL device was found"),
+            ComputeError::ContextCreationFailed => {
+                write!(f, "failed to create an OpenCL context/command queue")
+            }
+            ComputeError::KernelBuildFailed(msg) => write!(f, "kernel build failed: {msg}"),
+            ComputeError::Enqueue(msg) => write!(f, "enqueue failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ComputeError {}
+
+/// `Compute` owns an OpenCL context, command queue, and the compiled program backing this
+/// crate's per-pixel kernels. Construct one with [`Compute::new`] and reuse it across calls.
+///
+pub struct Compute {
+    queue: CommandQueue,
+    program: Program,
+    svm_capable: bool,
+}
+
+impl Compute {
+    /// `new` selects the first GPU device of the first OpenCL platform (other platforms are not
+    /// searched), compiles the built-in kernels against it,
+    /// and reports whether the device supports shared virtual memory.
+    ///
+    pub fn new() -> Result<Compute, ComputeError> {
+        let platforms = get_platforms().map_err(|_| ComputeError::NoPlatform)?;
+        let platform = platforms.first().ok_or(ComputeError::NoPlatform)?;
+
+        let device_id = platform
+            .get_devices(CL_DEVICE_TYPE_GPU)
+            .map_err(|_| ComputeError::NoDevice)?
+            .into_iter()
+            .next()
+            .ok_or(ComputeError::NoDevice)?;
+
+        let device = ClDevice::new(device_id);
+        let context =
+            Context::from_device(&device).map_err(|_| ComputeError::ContextCreationFailed)?;
+        let queue = CommandQueue::create_default_with_properties(&context, 0, 0)
+            .map_err(|_| ComputeError::ContextCreationFailed)?;
+
+        let program = Program::create_and_build_from_source(&context, GREYSCALE_KERNEL_SRC, "")
+            .map_err(ComputeError::KernelBuildFailed)?;
+
+        let svm_capable = device.svm_mem_capability() != 0;
+
+        Ok(Compute {
+            queue,
+            program,
+            svm_capable,
+        })
+    }
+
+    /// `greyscale` runs this crate's luma kernel (`0.21263901*r + 0.71516868*g + 0.07219232*b`)
+    /// over `r`, `g`, and `b`, which must all be the same length, and returns the resulting
+    /// single-channel buffer.
+    ///
+    pub fn greyscale(
+        &self,
+        r: &[u8],
+        g: &[u8],
+        b: &[u8],
+    ) -> Result<minivec::MiniVec<u8>, ComputeError> {
+        self.map3("greyscale", r, g, b)
+    }
+
+    /// `map3` is the generic per-pixel primitive `greyscale` is built on: it runs the kernel
+    /// named `kernel_name` (already compiled into `self.program`) over 3 same-length input
+    /// channels and returns one output channel of equal length.
+    ///
+    fn map3(
+        &self,
+        kernel_name: &str,
+        r: &[u8],
+        g: &[u8],
+        b: &[u8],
+    ) -> Result<minivec::MiniVec<u8>, ComputeError> {
+        let len = r.len();
+
+        if self.svm_capable {
+            return self.map3_svm(kernel_name, r, g, b);
+        }
+
+        let mut r_buf = unsafe {
+            Buffer::<cl_uchar>::create(self.queue.context(), CL_MEM_READ_ONLY, len, std::ptr::null_mut())
+        }
+        .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let mut g_buf = unsafe {
+            Buffer::<cl_uchar>::create(self.queue.context(), CL_MEM_READ_ONLY, len, std::ptr::null_mut())
+        }
+        .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let mut b_buf = unsafe {
+            Buffer::<cl_uchar>::create(self.queue.context(), CL_MEM_READ_ONLY, len, std::ptr::null_mut())
+        }
+        .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let out_buf = unsafe {
+            Buffer::<cl_uchar>::create(self.queue.context(), CL_MEM_WRITE_ONLY, len, std::ptr::null_mut())
+        }
+        .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        unsafe {
+            self.queue
+                .enqueue_write_buffer(&mut r_buf, CL_BLOCKING, 0, r, &[])
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+            self.queue
+                .enqueue_write_buffer(&mut g_buf, CL_BLOCKING, 0, g, &[])
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+            self.queue
+                .enqueue_write_buffer(&mut b_buf, CL_BLOCKING, 0, b, &[])
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        }
+
+        let kernel = Kernel::create(&self.program, kernel_name)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        let event = unsafe {
+            ExecuteKernel::new(&kernel)
+                .set_arg(&r_buf)
+                .set_arg(&g_buf)
+                .set_arg(&b_buf)
+                .set_arg(&out_buf)
+                .set_global_work_size(len)
+                .enqueue_nd_range(&self.queue)
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?
+        };
+        event.wait().map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        let mut out = minivec::MiniVec::<u8>::with_capacity(len);
+        unsafe {
+            let out_ptr = out.spare_capacity_mut().as_mut_ptr().cast::<u8>();
+            let out_slice = std::slice::from_raw_parts_mut(out_ptr, len);
+
+            self.queue
+                .enqueue_read_buffer(&out_buf, CL_BLOCKING, 0, out_slice, &[])
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+            out.set_len(len);
+        }
+
+        Ok(out)
+    }
+
+    /// `map3_svm` is the shared-virtual-memory fast path of [`Compute::map3`]: the host
+    /// allocates buffers the device can read and write directly, so no `enqueue_read_buffer`
+    /// call is needed once the kernel completes. The SVM contents are still copied into a
+    /// `MiniVec` before returning, since that's the buffer type this crate's public API uses.
+    ///
+    fn map3_svm(
+        &self,
+        kernel_name: &str,
+        r: &[u8],
+        g: &[u8],
+        b: &[u8],
+    ) -> Result<minivec::MiniVec<u8>, ComputeError> {
+        let len = r.len();
+        let context = self.queue.context();
+
+        let mut r_svm = SvmVec::<cl_uchar>::allocate(context, len)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let mut g_svm = SvmVec::<cl_uchar>::allocate(context, len)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let mut b_svm = SvmVec::<cl_uchar>::allocate(context, len)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+        let mut out_svm = SvmVec::<cl_uchar>::allocate(context, len)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        r_svm.as_mut_slice().copy_from_slice(r);
+        g_svm.as_mut_slice().copy_from_slice(g);
+        b_svm.as_mut_slice().copy_from_slice(b);
+
+        let kernel = Kernel::create(&self.program, kernel_name)
+            .map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        let event = unsafe {
+            ExecuteKernel::new(&kernel)
+                .set_arg_svm(r_svm.as_ptr())
+                .set_arg_svm(g_svm.as_ptr())
+                .set_arg_svm(b_svm.as_ptr())
+                .set_arg_svm(out_svm.as_mut_ptr())
+                .set_global_work_size(len)
+                .enqueue_nd_range(&self.queue)
+                .map_err(|e| ComputeError::Enqueue(e.to_string()))?
+        };
+        event.wait().map_err(|e| ComputeError::Enqueue(e.to_string()))?;
+
+        let mut out = minivec::MiniVec::<u8>::with_capacity(len);
+        unsafe {
+            out.spare_capacity_mut()[..len]
+                .iter_mut()
+                .zip(out_svm.as_slice())
+                .for_each(|(dst, &src)| dst.as_mut_ptr().write(src));
+            out.set_len(len);
+        }
+
+        Ok(out)
+    }
+}
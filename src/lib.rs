@@ -2,120 +2,239 @@
 
 extern crate minivec;
 
+#[cfg(feature = "compute")]
+pub mod compute;
 pub mod png;
+pub mod qoi;
 
-/// `RgbImg` represents an 8-bit RGB image. The image data is stored in a channel-major order so
-/// that is more easily translates to SIMD/SIMT architectures (consider something like CUDA).
+/// `Image` is a channel-major image buffer generic over its sample type `T` (e.g. `u8`, `u16`)
+/// and its number of channels `CHANNELS` (e.g. 3 for RGB, 4 for RGBA). Each channel is stored in
+/// its own contiguous allocation so that it more easily translates to SIMD/SIMT architectures
+/// (consider something like CUDA).
 ///
-pub struct RgbImg {
-    r: minivec::MiniVec<u8>,
-    g: minivec::MiniVec<u8>,
-    b: minivec::MiniVec<u8>,
+/// `RgbImg`, `RgbaImg`, and `Rgb16Img` are the aliases most callers want. Only `RgbImg` has
+/// channel-specific accessors (`r()`/`g()`/`b()`, `iter()`, ...) layered on top of this type;
+/// `RgbaImg` and `Rgb16Img` have only the generic methods of `Image` (`channel(idx)`, `total()`,
+/// ...) and no iterator.
+///
+pub struct Image<T, const CHANNELS: usize> {
+    channels: [minivec::MiniVec<T>; CHANNELS],
     height: usize,
     width: usize,
 }
 
-impl RgbImg {
-    /// `new` returns an empty `RgbImg` that has allocated no memory and has a height and width of
+/// `RgbImg` represents an 8-bit RGB image.
+///
+pub type RgbImg = Image<u8, 3>;
+
+/// `RgbaImg` represents an 8-bit RGBA image.
+///
+pub type RgbaImg = Image<u8, 4>;
+
+/// `Rgb16Img` represents a 16-bit RGB image, as used by medical and HDR scans.
+///
+pub type Rgb16Img = Image<u16, 3>;
+
+impl<T, const CHANNELS: usize> Image<T, CHANNELS>
+where
+    T: Copy,
+{
+    /// `new` returns an empty `Image` that has allocated no memory and has a height and width of
     /// `0`.
     ///
-    pub fn new() -> RgbImg {
-        RgbImg {
-            r: minivec::MiniVec::new(),
-            g: minivec::MiniVec::new(),
-            b: minivec::MiniVec::new(),
+    pub fn new() -> Self {
+        Self {
+            channels: std::array::from_fn(|_| minivec::MiniVec::new()),
             height: 0,
             width: 0,
         }
     }
 
-    /// `from_packed_buf` will construct an `RgbImg` from a user-supplied buffer, using the provided
-    /// `height` and `width` for image dimensions.
+    /// `from_packed_buf` will construct an `Image` from a user-supplied buffer, using the
+    /// provided `height` and `width` for image dimensions.
     ///
-    /// Internally `RgbImg` stores its image data across 3 allocations in a channel-major ordering.
-    /// But many libraries operate natively in terms of row-major densely packed image data so this
-    /// function is meant to help inter-operate with them.
+    /// Internally `Image` stores its image data across `CHANNELS` allocations in a channel-major
+    /// ordering. But many libraries operate natively in terms of row-major densely packed image
+    /// data, interleaving `CHANNELS` samples of `T` per pixel, so this function is meant to help
+    /// inter-operate with them.
     ///
     /// Note: this function _copies_ the supplied buf so its time and space complexity are both
     /// `O(buf.len())`.
     ///
-    pub fn from_packed_buf(buf: &[u8], height: usize, width: usize) -> RgbImg {
+    pub fn from_packed_buf(buf: &[T], height: usize, width: usize) -> Self {
         let total = height * width;
 
-        let mut r = minivec::MiniVec::<u8>::with_capacity(total);
-        let mut g = minivec::MiniVec::<u8>::with_capacity(total);
-        let mut b = minivec::MiniVec::<u8>::with_capacity(total);
+        let mut channels: [minivec::MiniVec<T>; CHANNELS] =
+            std::array::from_fn(|_| minivec::MiniVec::<T>::with_capacity(total));
 
-        let (r_buf, g_buf, b_buf) = (
-            r.spare_capacity_mut(),
-            g.spare_capacity_mut(),
-            b.spare_capacity_mut(),
-        );
-
-        buf.chunks_exact(3)
+        buf.chunks_exact(CHANNELS)
             .enumerate()
             .for_each(|(idx, pixel)| -> () {
-                unsafe {
-                    r_buf[idx].as_mut_ptr().write(pixel[0]);
-                    g_buf[idx].as_mut_ptr().write(pixel[1]);
-                    b_buf[idx].as_mut_ptr().write(pixel[2]);
+                for (c, sample) in channels.iter_mut().zip(pixel.iter()) {
+                    unsafe {
+                        c.spare_capacity_mut()[idx].as_mut_ptr().write(*sample);
+                    }
                 }
             });
 
-        unsafe {
-            r.set_len(total);
-            g.set_len(total);
-            b.set_len(total);
+        for c in &mut channels {
+            unsafe {
+                c.set_len(total);
+            }
         }
 
         Self {
-            r,
-            g,
-            b,
+            channels,
             height,
             width,
         }
     }
 
-    /// `to_packed_buf` writes the contained RGB data into a single contiguous buffer and then
-    /// returns it to the caller.
+    /// `to_packed_buf` writes the contained channel data into a single contiguous,
+    /// `CHANNELS`-interleaved buffer and then returns it to the caller.
     ///
-    /// Internally, `RgbImg` stores all of its data in a channel-major order using 3 separate
-    /// allocations. While this is an ideal layout for most operations, sometimes a densely-packed
-    /// row-major ordering of image data is required.
+    /// Internally, `Image` stores all of its data in a channel-major order using `CHANNELS`
+    /// separate allocations. While this is an ideal layout for most operations, sometimes a
+    /// densely-packed row-major ordering of image data is required.
     ///
-    pub fn to_packed_buf(&self) -> Vec<u8> {
-        let (r, g, b) = (self.r(), self.g(), self.b());
-        let len = self.total() as usize * 3;
-        let mut vec = vec![std::mem::MaybeUninit::<u8>::uninit(); len];
+    pub fn to_packed_buf(&self) -> Vec<T> {
+        let total = self.total();
+        let mut vec = vec![std::mem::MaybeUninit::<T>::uninit(); total * CHANNELS];
 
-        for idx in 0..self.total() as usize {
-            let base_offset = idx as usize * 3;
+        for idx in 0..total {
+            let base_offset = idx * CHANNELS;
 
-            vec[base_offset + 0] = std::mem::MaybeUninit::new(r[idx]);
-            vec[base_offset + 1] = std::mem::MaybeUninit::new(g[idx]);
-            vec[base_offset + 2] = std::mem::MaybeUninit::new(b[idx]);
+            for (c, channel) in self.channels.iter().enumerate() {
+                vec[base_offset + c] = std::mem::MaybeUninit::new(channel[idx]);
+            }
         }
 
         let mut vec = core::mem::ManuallyDrop::new(vec);
-        unsafe { Vec::from_raw_parts(vec.as_mut_ptr() as *mut u8, vec.len(), vec.capacity()) }
+        unsafe { Vec::from_raw_parts(vec.as_mut_ptr().cast::<T>(), vec.len(), vec.capacity()) }
+    }
+
+    /// `channel` will return a read-only slice pointing to the image data for the channel at
+    /// `idx` (e.g. `0` for red, `1` for green, `2` for blue).
+    ///
+    pub fn channel(&self, idx: usize) -> &[T] {
+        &self.channels[idx]
     }
 
+    /// `height` returns the number of rows contained in the image data.
+    ///
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// `width` returns the number of columns contained in the image data.
+    ///
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// `total` is named after its OpenCV counterpart and returns the number of pixels contained
+    /// in the image data.
+    ///
+    pub fn total(&self) -> usize {
+        self.height() * self.width()
+    }
+}
+
+impl RgbImg {
     /// `r` will return a read-only slice pointing to the image data's red channel.
     ///
     pub fn r(&self) -> &[u8] {
-        &self.r
+        self.channel(0)
     }
 
     /// `g` will return a read-only slice pointing to the image data's green channel.
     ///
     pub fn g(&self) -> &[u8] {
-        &self.g
+        self.channel(1)
     }
 
     /// `b` will return a read-only slice pointing to the image data's blue channel.
     pub fn b(&self) -> &[u8] {
-        &self.b
+        self.channel(2)
+    }
+
+    /// `iter` returns an `RgbIter` over the current image data. The iterator returns a tuple
+    /// `(u8, u8, u8)` in `(R, G, B)` ordering.
+    ///
+    pub fn iter(&self) -> RgbIter {
+        RgbIter {
+            r_: self.r().iter(),
+            g_: self.g().iter(),
+            b_: self.b().iter(),
+        }
+    }
+
+    /// `resize` returns a new `RgbImg` scaled to `new_width` x `new_height` pixels using the
+    /// supplied resampling `filter`.
+    ///
+    /// This is implemented as a separable two-pass resample: the image is first scaled
+    /// horizontally into an intermediate buffer, then vertically. Because `r`, `g`, and `b` are
+    /// already distinct contiguous buffers, each channel is resampled independently with
+    /// identical code.
+    ///
+    pub fn resize(&self, new_width: usize, new_height: usize, filter: Filter) -> RgbImg {
+        let (width, height) = (self.width, self.height);
+
+        RgbImg {
+            channels: [
+                resize_channel(self.r(), width, height, new_width, new_height, filter),
+                resize_channel(self.g(), width, height, new_width, new_height, filter),
+                resize_channel(self.b(), width, height, new_width, new_height, filter),
+            ],
+            height: new_height,
+            width: new_width,
+        }
+    }
+
+    /// `greyscale_gpu` runs this crate's luma kernel on an OpenCL device and returns the
+    /// resulting single-channel buffer, leaving the CPU-only [`Greyscale`] iterator as the
+    /// dependency-free default.
+    ///
+    #[cfg(feature = "compute")]
+    pub fn greyscale_gpu(&self) -> Result<minivec::MiniVec<u8>, compute::ComputeError> {
+        compute::Compute::new()?.greyscale(self.r(), self.g(), self.b())
+    }
+}
+
+/// `Channel` identifies one of the three channels held by a `SharedRgbImg`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    R,
+    G,
+    B,
+}
+
+/// `SharedRgbImg` is a reference-counted variant of `RgbImg`. Where `RgbImg` owns its 3 channel
+/// buffers outright, `SharedRgbImg` backs each channel with an `Arc<[u8]>`, so `clone()` bumps a
+/// refcount instead of copying the underlying bytes. This suits pipelines that fan an image out
+/// to several concurrent stages, each of which only needs to read the data.
+///
+pub struct SharedRgbImg {
+    channels: [std::sync::Arc<[u8]>; 3],
+    height: usize,
+    width: usize,
+}
+
+impl SharedRgbImg {
+    /// `from_rgb_img` copies `img`'s 3 channel buffers once into ref-counted allocations,
+    /// returning a `SharedRgbImg` that can then be cloned at `O(1)`.
+    ///
+    pub fn from_rgb_img(img: &RgbImg) -> SharedRgbImg {
+        SharedRgbImg {
+            channels: [
+                std::sync::Arc::from(img.r()),
+                std::sync::Arc::from(img.g()),
+                std::sync::Arc::from(img.b()),
+            ],
+            height: img.height(),
+            width: img.width(),
+        }
     }
 
     /// `height` returns the number of rows contained in the image data.
@@ -137,16 +256,243 @@ impl RgbImg {
         self.height() * self.width()
     }
 
-    /// `iter` returns an `RgbIter` over the current image data. The iterator returns a tuple
-    /// `(u8, u8, u8)` in `(R, G, B)` ordering.
+    /// `r` will return a read-only slice pointing to the image data's red channel.
     ///
-    pub fn iter(&self) -> RgbIter {
-        RgbIter {
-            r_: self.r().iter(),
-            g_: self.g().iter(),
-            b_: self.b().iter(),
+    pub fn r(&self) -> &[u8] {
+        &self.channels[Channel::R as usize]
+    }
+
+    /// `g` will return a read-only slice pointing to the image data's green channel.
+    ///
+    pub fn g(&self) -> &[u8] {
+        &self.channels[Channel::G as usize]
+    }
+
+    /// `b` will return a read-only slice pointing to the image data's blue channel.
+    ///
+    pub fn b(&self) -> &[u8] {
+        &self.channels[Channel::B as usize]
+    }
+
+    /// `channel_view` returns a `SharedChannel`: a cheaply-clonable handle onto a single
+    /// channel's backing memory that shares the same allocation as `self`, rather than copying
+    /// it out into a new buffer.
+    ///
+    pub fn channel_view(&self, channel: Channel) -> SharedChannel {
+        SharedChannel {
+            data: std::sync::Arc::clone(&self.channels[channel as usize]),
+        }
+    }
+}
+
+impl Clone for SharedRgbImg {
+    fn clone(&self) -> SharedRgbImg {
+        SharedRgbImg {
+            channels: [
+                std::sync::Arc::clone(&self.channels[0]),
+                std::sync::Arc::clone(&self.channels[1]),
+                std::sync::Arc::clone(&self.channels[2]),
+            ],
+            height: self.height,
+            width: self.width,
+        }
+    }
+}
+
+/// `SharedChannel` is a cheaply-clonable, ref-counted handle onto a single channel's backing
+/// memory, usable as `&[u8]` via `Deref`. The memory is freed once the last handle (whether
+/// that's the originating `SharedRgbImg` or a `SharedChannel` view) drops.
+///
+pub struct SharedChannel {
+    data: std::sync::Arc<[u8]>,
+}
+
+impl std::ops::Deref for SharedChannel {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Clone for SharedChannel {
+    fn clone(&self) -> SharedChannel {
+        SharedChannel {
+            data: std::sync::Arc::clone(&self.data),
+        }
+    }
+}
+
+/// `Filter` selects the resampling kernel used by `RgbImg::resize`.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Picks the closest source sample; cheapest and blockiest.
+    Nearest,
+    /// Linear interpolation (a triangle of support radius 1).
+    Triangle,
+    /// Mitchell-Netravali cubic with `B=0, C=0.5` (support radius 2).
+    CatmullRom,
+    /// A truncated Gaussian (support radius 3).
+    Gaussian,
+    /// A windowed sinc with a 3-lobe window (support radius 3).
+    Lanczos3,
+}
+
+impl Filter {
+    fn support(self) -> f32 {
+        match self {
+            Filter::Nearest => 0.5,
+            Filter::Triangle => 1.0,
+            Filter::CatmullRom => 2.0,
+            Filter::Gaussian => 3.0,
+            Filter::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        match self {
+            Filter::Nearest => {
+                if x.abs() < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Triangle => (1.0 - x.abs()).max(0.0),
+            Filter::CatmullRom => {
+                let x = x.abs();
+                if x < 1.0 {
+                    1.5 * x * x * x - 2.5 * x * x + 1.0
+                } else if x < 2.0 {
+                    -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Gaussian => {
+                let x = x.abs();
+                if x < 3.0 {
+                    (-2.0 * x * x).exp() * (2.0 / std::f32::consts::PI).sqrt()
+                } else {
+                    0.0
+                }
+            }
+            Filter::Lanczos3 => {
+                let x = x.abs();
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// `AxisWeights` holds the precomputed filter weights that contribute to a single output sample
+/// along one axis, starting at source index `left`.
+///
+struct AxisWeights {
+    left: usize,
+    weights: Vec<f32>,
+}
+
+/// `compute_axis_weights` precomputes, for every output index along an axis of length
+/// `dst_size`, the source indices and weights that contribute to it. Downscaling widens the
+/// filter support by `src_size / dst_size` so the result stays anti-aliased.
+///
+/// `Filter::Nearest` is handled separately: it always selects the single closest source index
+/// with weight `1.0`, rather than widening its support and averaging a window like every other
+/// filter does.
+///
+fn compute_axis_weights(src_size: usize, dst_size: usize, filter: Filter) -> Vec<AxisWeights> {
+    let scale = src_size as f32 / dst_size as f32;
+
+    if filter == Filter::Nearest {
+        return (0..dst_size)
+            .map(|o| {
+                let center = (o as f32 + 0.5) * scale - 0.5;
+                let nearest = center.round().clamp(0.0, (src_size - 1) as f32) as usize;
+
+                AxisWeights {
+                    left: nearest,
+                    weights: vec![1.0],
+                }
+            })
+            .collect();
+    }
+
+    let filter_scale = scale.max(1.0);
+    let radius = filter.support() * filter_scale;
+
+    (0..dst_size)
+        .map(|o| {
+            let center = (o as f32 + 0.5) * scale - 0.5;
+            let left = (center - radius).floor().max(0.0) as usize;
+            let right = ((center + radius).ceil() as usize).min(src_size.saturating_sub(1));
+
+            let weights = (left..=right)
+                .map(|s| filter.weight((s as f32 - center) / filter_scale))
+                .collect();
+
+            AxisWeights { left, weights }
+        })
+        .collect()
+}
+
+/// `resize_channel` resamples a single channel-major `u8` plane from `(src_w, src_h)` to
+/// `(dst_w, dst_h)` using a two-pass (horizontal, then vertical) separable resample.
+///
+fn resize_channel(
+    src: &[u8],
+    src_w: usize,
+    src_h: usize,
+    dst_w: usize,
+    dst_h: usize,
+    filter: Filter,
+) -> minivec::MiniVec<u8> {
+    let col_weights = compute_axis_weights(src_w, dst_w, filter);
+    let row_weights = compute_axis_weights(src_h, dst_h, filter);
+
+    let mut horiz = vec![0.0f32; src_h * dst_w];
+    for row in 0..src_h {
+        let src_row = &src[row * src_w..(row + 1) * src_w];
+        for (col, axis) in col_weights.iter().enumerate() {
+            let mut acc = 0.0f32;
+            let mut wsum = 0.0f32;
+            for (k, &weight) in axis.weights.iter().enumerate() {
+                acc += weight * f32::from(src_row[axis.left + k]);
+                wsum += weight;
+            }
+            horiz[row * dst_w + col] = if wsum != 0.0 { acc / wsum } else { 0.0 };
         }
     }
+
+    let mut out = minivec::MiniVec::<u8>::with_capacity(dst_w * dst_h);
+    for axis in &row_weights {
+        for col in 0..dst_w {
+            let mut acc = 0.0f32;
+            let mut wsum = 0.0f32;
+            for (k, &weight) in axis.weights.iter().enumerate() {
+                acc += weight * horiz[(axis.left + k) * dst_w + col];
+                wsum += weight;
+            }
+            let v = if wsum != 0.0 { acc / wsum } else { 0.0 };
+            out.push(v.round().clamp(0.0, 255.0) as u8);
+        }
+    }
+
+    out
 }
 
 pub struct RgbIter<'a> {
@@ -223,3 +569,98 @@ where
 }
 
 impl<Iter> Greyscale for Iter where Iter: std::iter::Iterator<Item = (u8, u8, u8)> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_img_round_trips_through_a_packed_buf() {
+        #[rustfmt::skip]
+        let packed: [u8; 8] = [
+            10, 20, 30, 40,
+            50, 60, 70, 80,
+        ];
+        let img = RgbaImg::from_packed_buf(&packed, 1, 2);
+
+        assert_eq!(img.channel(0), &[10, 50]);
+        assert_eq!(img.channel(1), &[20, 60]);
+        assert_eq!(img.channel(2), &[30, 70]);
+        assert_eq!(img.channel(3), &[40, 80]);
+        assert_eq!(img.to_packed_buf(), packed);
+    }
+
+    #[test]
+    fn rgb16_img_round_trips_through_a_packed_buf() {
+        #[rustfmt::skip]
+        let packed: [u16; 6] = [
+            1000, 2000, 3000,
+            4000, 5000, 6000,
+        ];
+        let img = Rgb16Img::from_packed_buf(&packed, 1, 2);
+
+        assert_eq!(img.channel(0), &[1000, 4000]);
+        assert_eq!(img.channel(1), &[2000, 5000]);
+        assert_eq!(img.channel(2), &[3000, 6000]);
+        assert_eq!(img.to_packed_buf(), packed);
+    }
+
+    #[test]
+    fn shared_rgb_img_clone_and_channel_view_alias_the_same_allocation() {
+        let packed: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let img = RgbImg::from_packed_buf(&packed, 1, 3);
+        let shared = SharedRgbImg::from_rgb_img(&img);
+
+        let cloned = shared.clone();
+        let view = shared.channel_view(Channel::R);
+
+        assert_eq!(shared.r().as_ptr(), cloned.r().as_ptr());
+        assert_eq!(shared.r().as_ptr(), view.as_ptr());
+        assert_eq!(&*view, shared.r());
+
+        drop(shared);
+        drop(cloned);
+
+        // The backing allocation must survive as long as any handle (here, `view`) is alive.
+        assert_eq!(&*view, &[1, 4, 7]);
+    }
+
+    fn resize_column(pixels: &[u8], src_len: usize, dst_len: usize, filter: Filter) -> Vec<u8> {
+        resize_channel(pixels, 1, src_len, 1, dst_len, filter).to_vec()
+    }
+
+    #[test]
+    fn resize_is_identity_for_every_interpolating_filter_when_dimensions_are_unchanged() {
+        // Gaussian is excluded: it has no zero crossings at integer offsets, so it blurs even at
+        // a 1:1 scale. The others are interpolating kernels (weight 1 at 0, weight 0 at every
+        // other integer), so resampling onto the same grid must reproduce it exactly.
+        let column = [10u8, 20, 30, 40];
+
+        for filter in [
+            Filter::Nearest,
+            Filter::Triangle,
+            Filter::CatmullRom,
+            Filter::Lanczos3,
+        ] {
+            assert_eq!(resize_column(&column, 4, 4, filter), column, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn nearest_picks_a_real_sample_instead_of_averaging() {
+        let column = [10u8, 20, 30, 40];
+
+        let resized = resize_column(&column, 4, 2, Filter::Nearest);
+
+        for v in &resized {
+            assert!(column.contains(v), "{v} is not one of {column:?}");
+        }
+    }
+
+    #[test]
+    fn triangle_downscale_matches_a_known_value() {
+        let column = [10u8, 20, 30, 40];
+
+        assert_eq!(resize_column(&column, 4, 2, Filter::Triangle), [17, 33]);
+    }
+}
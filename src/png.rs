@@ -0,0 +1,427 @@
+//! `png` decodes 8-bit PNG images into an `RgbImg`, including Adam7-interlaced PNGs.
+//!
+//! Adam7 splits the image into 7 passes over an 8x8 tile grid, each pass being a smaller
+//! sub-image that must be unfiltered independently and then scattered into the full image at
+//! `(y0 + row*dy, x0 + col*dx)`. [`InterlaceHandling::SparkleRows`] lets a caller observe a
+//! coarse preview of the image after each pass completes, which is useful when streaming large,
+//! interlaced PNGs; [`InterlaceHandling::Final`] only delivers the fully deinterlaced image.
+
+use crate::RgbImg;
+
+mod inflate;
+
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// `(x0, y0, dx, dy)` starting offset and stride, per Adam7 pass, over the 8x8 tile grid.
+///
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// `InterlaceHandling` controls what a caller of [`decode_with`] observes while an
+/// Adam7-interlaced PNG is being decoded.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterlaceHandling {
+    /// Only the fully deinterlaced image is delivered, via `decode_with`'s return value.
+    Final,
+    /// After every completed Adam7 pass, the callback is invoked with a coarse preview of the
+    /// image as decoded so far.
+    SparkleRows,
+}
+
+/// `decode` parses a buffer of PNG-formatted bytes and reconstructs the `RgbImg` it describes,
+/// transparently deinterlacing Adam7 images.
+///
+pub fn decode(buf: &[u8]) -> RgbImg {
+    decode_with(buf, InterlaceHandling::Final, |_img| {})
+}
+
+/// `decode_with` is [`decode`], but with `handling` controlling whether `on_pass` is invoked
+/// with a progressive preview after each Adam7 pass. `handling` has no effect on non-interlaced
+/// PNGs, since they have only a single pass.
+///
+pub fn decode_with<F>(buf: &[u8], handling: InterlaceHandling, mut on_pass: F) -> RgbImg
+where
+    F: FnMut(&RgbImg),
+{
+    assert_eq!(&buf[0..8], &PNG_SIGNATURE, "not a PNG file");
+
+    let mut pos = 8;
+    let mut width = 0usize;
+    let mut height = 0usize;
+    let mut bit_depth = 0u8;
+    let mut color_type = 0u8;
+    let mut interlace = 0u8;
+    let mut idat = Vec::new();
+
+    while pos < buf.len() {
+        let len = u32::from_be_bytes(buf[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &buf[pos + 4..pos + 8];
+        let data = &buf[pos + 8..pos + 8 + len];
+
+        match kind {
+            b"IHDR" => {
+                width = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                height = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+                bit_depth = data[8];
+                color_type = data[9];
+                interlace = data[12];
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+
+        pos += 8 + len + 4;
+    }
+
+    assert_eq!(bit_depth, 8, "unsupported PNG bit depth: {bit_depth} (only 8-bit is supported)");
+    assert!(
+        idat.len() >= 6,
+        "PNG has no usable IDAT data (zlib stream needs at least a 2-byte header and 4-byte trailer)"
+    );
+
+    let channels = color_type_channels(color_type);
+    let raw = inflate::inflate(&idat[2..idat.len() - 4]);
+
+    let mut r = vec![0u8; width * height];
+    let mut g = vec![0u8; width * height];
+    let mut b = vec![0u8; width * height];
+
+    if interlace == 0 {
+        let plane = unfilter(&raw, width, height, channels);
+        scatter(&plane, width, height, channels, 0, 0, 1, 1, width, &mut r, &mut g, &mut b);
+    } else {
+        let mut offset = 0;
+
+        for &(x0, y0, dx, dy) in &ADAM7_PASSES {
+            let (pass_w, pass_h) = adam7_pass_dims(width, height, x0, dx, y0, dy);
+            if pass_w == 0 || pass_h == 0 {
+                continue;
+            }
+
+            let pass_len = (pass_w * channels + 1) * pass_h;
+            let plane = unfilter(&raw[offset..offset + pass_len], pass_w, pass_h, channels);
+            offset += pass_len;
+
+            scatter(&plane, pass_w, pass_h, channels, x0, y0, dx, dy, width, &mut r, &mut g, &mut b);
+
+            if handling == InterlaceHandling::SparkleRows {
+                on_pass(&RgbImg::from_packed_buf(&interleave_rgb(&r, &g, &b), height, width));
+            }
+        }
+    }
+
+    RgbImg::from_packed_buf(&interleave_rgb(&r, &g, &b), height, width)
+}
+
+fn color_type_channels(color_type: u8) -> usize {
+    match color_type {
+        0 => 1, // greyscale
+        2 => 3, // truecolor (RGB)
+        4 => 2, // greyscale + alpha
+        6 => 4, // truecolor + alpha (RGBA)
+        _ => panic!("unsupported PNG color type: {color_type}"),
+    }
+}
+
+/// `adam7_pass_dims` returns the `(width, height)` of the sub-image covered by one Adam7 pass
+/// starting at `(x0, y0)` with stride `(dx, dy)` over a full image of `(width, height)`.
+///
+fn adam7_pass_dims(
+    width: usize,
+    height: usize,
+    x0: usize,
+    dx: usize,
+    y0: usize,
+    dy: usize,
+) -> (usize, usize) {
+    let pass_w = if width > x0 { (width - x0 + dx - 1) / dx } else { 0 };
+    let pass_h = if height > y0 { (height - y0 + dy - 1) / dy } else { 0 };
+
+    (pass_w, pass_h)
+}
+
+/// `unfilter` reverses the per-scanline PNG filters (None, Sub, Up, Average, Paeth) applied to
+/// `data`, an 8-bit `width`x`height` image with `channels` samples per pixel.
+///
+fn unfilter(data: &[u8], width: usize, height: usize, channels: usize) -> Vec<u8> {
+    let stride = width * channels;
+    let mut out = vec![0u8; stride * height];
+    let mut pos = 0;
+
+    for row in 0..height {
+        let filter_type = data[pos];
+        pos += 1;
+
+        let src = &data[pos..pos + stride];
+        pos += stride;
+
+        let row_start = row * stride;
+        let has_prev = row > 0;
+        let prev_start = row_start.wrapping_sub(stride);
+
+        for x in 0..stride {
+            let a = if x >= channels { out[row_start + x - channels] } else { 0 };
+            let b = if has_prev { out[prev_start + x] } else { 0 };
+            let c = if has_prev && x >= channels { out[prev_start + x - channels] } else { 0 };
+
+            out[row_start + x] = match filter_type {
+                0 => src[x],
+                1 => src[x].wrapping_add(a),
+                2 => src[x].wrapping_add(b),
+                3 => src[x].wrapping_add(((u16::from(a) + u16::from(b)) / 2) as u8),
+                4 => src[x].wrapping_add(paeth(a, b, c)),
+                _ => panic!("unsupported PNG filter type: {filter_type}"),
+            };
+        }
+    }
+
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let (a, b, c) = (i32::from(a), i32::from(b), i32::from(c));
+    let p = a + b - c;
+
+    let pa = (p - a).abs();
+    let pb = (p - b).abs();
+    let pc = (p - c).abs();
+
+    if pa <= pb && pa <= pc {
+        a as u8
+    } else if pb <= pc {
+        b as u8
+    } else {
+        c as u8
+    }
+}
+
+/// `scatter` writes an unfiltered, channel-interleaved `pass_w`x`pass_h` sub-image into the full
+/// `width`-wide channel-major `r`/`g`/`b` buffers, placing source pixel `(row, col)` at
+/// `(y0 + row*dy, x0 + col*dx)` in the destination. Greyscale sources (`channels` of 1 or 2) are
+/// replicated across `r`, `g`, and `b`; an alpha channel (the 2nd of 2 or the 4th of 4), if
+/// present, is dropped.
+///
+#[allow(clippy::too_many_arguments)]
+fn scatter(
+    plane: &[u8],
+    pass_w: usize,
+    pass_h: usize,
+    channels: usize,
+    x0: usize,
+    y0: usize,
+    dx: usize,
+    dy: usize,
+    width: usize,
+    r: &mut [u8],
+    g: &mut [u8],
+    b: &mut [u8],
+) {
+    let (g_off, b_off) = if channels >= 3 { (1, 2) } else { (0, 0) };
+
+    for row in 0..pass_h {
+        for col in 0..pass_w {
+            let src = (row * pass_w + col) * channels;
+            let dst = (y0 + row * dy) * width + (x0 + col * dx);
+
+            r[dst] = plane[src];
+            g[dst] = plane[src + g_off];
+            b[dst] = plane[src + b_off];
+        }
+    }
+}
+
+fn interleave_rgb(r: &[u8], g: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(r.len() * 3);
+
+    for i in 0..r.len() {
+        out.push(r[i]);
+        out.push(g[i]);
+        out.push(b[i]);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(tag);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&[0, 0, 0, 0]); // CRC isn't validated by `decode`.
+        out
+    }
+
+    /// `stored_deflate` wraps `data` in a single stored (uncompressed) DEFLATE block, which
+    /// `inflate::inflate` can unpack without needing a real Huffman-encoding encoder in tests.
+    fn stored_deflate(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x01]; // BFINAL=1, BTYPE=00 (stored)
+        let len = data.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn zlib_wrap(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x78, 0x01];
+        out.extend_from_slice(data);
+        out.extend_from_slice(&[0, 0, 0, 0]); // Adler-32 trailer isn't validated by `decode`.
+        out
+    }
+
+    fn build_png(width: u32, height: u32, color_type: u8, interlace: u8, raw: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&PNG_SIGNATURE);
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, color_type, 0, 0, interlace]);
+        out.extend_from_slice(&chunk(b"IHDR", &ihdr));
+
+        out.extend_from_slice(&chunk(b"IDAT", &zlib_wrap(&stored_deflate(raw))));
+        out.extend_from_slice(&chunk(b"IEND", &[]));
+
+        out
+    }
+
+    fn pixel(x: usize, y: usize) -> (u8, u8, u8) {
+        ((x * 10) as u8, (y * 20) as u8, ((x + y) * 5) as u8)
+    }
+
+    #[test]
+    fn decodes_a_non_interlaced_rgb_image() {
+        let raw = [
+            0, 10, 20, 30, 40, 50, 60, // row 0: (10,20,30) (40,50,60)
+            0, 70, 80, 90, 100, 110, 120, // row 1
+        ];
+        let img = decode(&build_png(2, 2, 2, 0, &raw));
+
+        assert_eq!((img.width(), img.height()), (2, 2));
+        assert_eq!(img.r(), &[10, 40, 70, 100]);
+        assert_eq!(img.g(), &[20, 50, 80, 110]);
+        assert_eq!(img.b(), &[30, 60, 90, 120]);
+    }
+
+    #[test]
+    fn decodes_an_adam7_interlaced_image_identically_to_non_interlaced() {
+        let (width, height) = (4usize, 4usize);
+
+        let mut flat = Vec::new();
+        for y in 0..height {
+            flat.push(0u8);
+            for x in 0..width {
+                let (r, g, b) = pixel(x, y);
+                flat.extend_from_slice(&[r, g, b]);
+            }
+        }
+        let non_interlaced = decode(&build_png(width as u32, height as u32, 2, 0, &flat));
+
+        let mut adam7 = Vec::new();
+        for &(x0, y0, dx, dy) in &ADAM7_PASSES {
+            let (pass_w, pass_h) = adam7_pass_dims(width, height, x0, dx, y0, dy);
+            if pass_w == 0 || pass_h == 0 {
+                continue;
+            }
+
+            for row in 0..pass_h {
+                adam7.push(0u8);
+                for col in 0..pass_w {
+                    let (r, g, b) = pixel(x0 + col * dx, y0 + row * dy);
+                    adam7.extend_from_slice(&[r, g, b]);
+                }
+            }
+        }
+        let interlaced = decode(&build_png(width as u32, height as u32, 2, 1, &adam7));
+
+        assert_eq!(interlaced.r(), non_interlaced.r());
+        assert_eq!(interlaced.g(), non_interlaced.g());
+        assert_eq!(interlaced.b(), non_interlaced.b());
+    }
+
+    #[test]
+    fn sparkle_rows_invokes_the_callback_with_full_size_previews() {
+        let (width, height) = (4usize, 4usize);
+
+        let mut adam7 = Vec::new();
+        for &(x0, y0, dx, dy) in &ADAM7_PASSES {
+            let (pass_w, pass_h) = adam7_pass_dims(width, height, x0, dx, y0, dy);
+            if pass_w == 0 || pass_h == 0 {
+                continue;
+            }
+
+            for row in 0..pass_h {
+                adam7.push(0u8);
+                for col in 0..pass_w {
+                    let (r, g, b) = pixel(x0 + col * dx, y0 + row * dy);
+                    adam7.extend_from_slice(&[r, g, b]);
+                }
+            }
+        }
+        let png = build_png(width as u32, height as u32, 2, 1, &adam7);
+
+        let mut previews = 0;
+        let img = decode_with(&png, InterlaceHandling::SparkleRows, |preview| {
+            assert_eq!((preview.width(), preview.height()), (width, height));
+            previews += 1;
+        });
+
+        assert!(previews > 0);
+        assert_eq!(img.r().len(), width * height);
+    }
+
+    #[test]
+    fn replicates_grey_and_drops_alpha_for_channels_below_three() {
+        // filter 0, then (grey=0, a=255), (grey=100, a=10)
+        let raw = [0u8, 0, 255, 100, 10];
+        let img = decode(&build_png(2, 1, 4, 0, &raw));
+
+        assert_eq!(img.r(), &[0, 100]);
+        assert_eq!(img.g(), &[0, 100]);
+        assert_eq!(img.b(), &[0, 100]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported PNG bit depth")]
+    fn rejects_non_8_bit_images() {
+        let mut out = Vec::new();
+        out.extend_from_slice(&PNG_SIGNATURE);
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&1u32.to_be_bytes());
+        ihdr.extend_from_slice(&1u32.to_be_bytes());
+        ihdr.extend_from_slice(&[16, 2, 0, 0, 0]); // bit depth 16
+        out.extend_from_slice(&chunk(b"IHDR", &ihdr));
+        out.extend_from_slice(&chunk(b"IDAT", &zlib_wrap(&stored_deflate(&[0, 0, 0, 0]))));
+        out.extend_from_slice(&chunk(b"IEND", &[]));
+
+        decode(&out);
+    }
+
+    #[test]
+    #[should_panic(expected = "no usable IDAT data")]
+    fn rejects_missing_idat_data() {
+        let mut out = Vec::new();
+        out.extend_from_slice(&PNG_SIGNATURE);
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&1u32.to_be_bytes());
+        ihdr.extend_from_slice(&1u32.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+        out.extend_from_slice(&chunk(b"IHDR", &ihdr));
+        out.extend_from_slice(&chunk(b"IEND", &[]));
+
+        decode(&out);
+    }
+}
@@ -0,0 +1,240 @@
+//! A small DEFLATE (RFC 1951) decoder, just capable enough to unpack the zlib-wrapped `IDAT`
+//! stream of a PNG. `inflate` expects the raw deflate stream with the 2-byte zlib header and
+//! 4-byte Adler-32 trailer already stripped off.
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// `BitReader` pulls LSB-first bits out of a byte slice, as DEFLATE requires.
+///
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u32,
+    nbits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, acc: 0, nbits: 0 }
+    }
+
+    fn read_bits(&mut self, n: u32) -> u32 {
+        while self.nbits < n {
+            self.acc |= u32::from(self.data[self.pos]) << self.nbits;
+            self.pos += 1;
+            self.nbits += 8;
+        }
+
+        let value = self.acc & ((1 << n) - 1);
+        self.acc >>= n;
+        self.nbits -= n;
+
+        value
+    }
+
+    fn align_to_byte(&mut self) {
+        self.acc = 0;
+        self.nbits = 0;
+    }
+}
+
+/// `HuffmanTable` is a canonical Huffman decoder built from a list of per-symbol code lengths,
+/// following the construction in Mark Adler's `puff.c` reference decoder.
+///
+struct HuffmanTable {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl HuffmanTable {
+    fn build(lengths: &[u8]) -> HuffmanTable {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..16 {
+            offsets[len] = offsets[len - 1] + counts[len - 1];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        HuffmanTable { counts, symbols }
+    }
+
+    fn decode(&self, r: &mut BitReader) -> u16 {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+
+        for len in 1..16 {
+            code |= r.read_bits(1) as i32;
+            let count = i32::from(self.counts[len]);
+
+            if code - first < count {
+                return self.symbols[(index + (code - first)) as usize];
+            }
+
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        unreachable!("invalid Huffman code in DEFLATE stream")
+    }
+}
+
+fn fixed_tables() -> (HuffmanTable, HuffmanTable) {
+    let mut lit_lengths = [0u8; 288];
+    for (i, len) in lit_lengths.iter_mut().enumerate() {
+        *len = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+
+    let dist_lengths = [5u8; 30];
+
+    (HuffmanTable::build(&lit_lengths), HuffmanTable::build(&dist_lengths))
+}
+
+fn dynamic_tables(r: &mut BitReader) -> (HuffmanTable, HuffmanTable) {
+    let hlit = r.read_bits(5) as usize + 257;
+    let hdist = r.read_bits(5) as usize + 1;
+    let hclen = r.read_bits(4) as usize + 4;
+
+    let mut cl_lengths = [0u8; 19];
+    for i in 0..hclen {
+        cl_lengths[CODE_LENGTH_ORDER[i]] = r.read_bits(3) as u8;
+    }
+
+    let cl_table = HuffmanTable::build(&cl_lengths);
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut i = 0;
+    while i < lengths.len() {
+        match cl_table.decode(r) {
+            sym @ 0..=15 => {
+                lengths[i] = sym as u8;
+                i += 1;
+            }
+            16 => {
+                let prev = lengths[i - 1];
+                let repeat = 3 + r.read_bits(2);
+                for _ in 0..repeat {
+                    lengths[i] = prev;
+                    i += 1;
+                }
+            }
+            17 => {
+                let repeat = 3 + r.read_bits(3);
+                for _ in 0..repeat {
+                    lengths[i] = 0;
+                    i += 1;
+                }
+            }
+            18 => {
+                let repeat = 11 + r.read_bits(7);
+                for _ in 0..repeat {
+                    lengths[i] = 0;
+                    i += 1;
+                }
+            }
+            _ => unreachable!("code-length alphabet only spans 0..=18"),
+        }
+    }
+
+    (
+        HuffmanTable::build(&lengths[..hlit]),
+        HuffmanTable::build(&lengths[hlit..]),
+    )
+}
+
+fn inflate_block(r: &mut BitReader, lit: &HuffmanTable, dist: &HuffmanTable, out: &mut Vec<u8>) {
+    loop {
+        match lit.decode(r) {
+            sym @ 0..=255 => out.push(sym as u8),
+            256 => return,
+            sym => {
+                let idx = (sym - 257) as usize;
+                let len = u32::from(LENGTH_BASE[idx]) + r.read_bits(LENGTH_EXTRA[idx]);
+
+                let dsym = dist.decode(r) as usize;
+                let distance = u32::from(DIST_BASE[dsym]) + r.read_bits(DIST_EXTRA[dsym]);
+
+                let start = out.len() - distance as usize;
+                for i in 0..len as usize {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+/// `inflate` decompresses a raw DEFLATE stream (stored, fixed-Huffman, and dynamic-Huffman
+/// blocks) in full.
+///
+pub(crate) fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut r = BitReader::new(data);
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = r.read_bits(1) == 1;
+        let block_type = r.read_bits(2);
+
+        match block_type {
+            0 => {
+                r.align_to_byte();
+
+                let len = u16::from(r.data[r.pos]) | (u16::from(r.data[r.pos + 1]) << 8);
+                r.pos += 4; // LEN and its one's-complement, NLEN
+
+                out.extend_from_slice(&r.data[r.pos..r.pos + len as usize]);
+                r.pos += len as usize;
+            }
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_block(&mut r, &lit, &dist, &mut out);
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut r);
+                inflate_block(&mut r, &lit, &dist, &mut out);
+            }
+            _ => panic!("invalid DEFLATE block type"),
+        }
+
+        if is_final {
+            break;
+        }
+    }
+
+    out
+}
@@ -0,0 +1,250 @@
+//! `qoi` implements encoding and decoding support for the [QOI](https://qoiformat.org/) image
+//! format, a simple byte-stream format that is competitive with PNG in size while being
+//! dramatically faster to encode and decode.
+
+use crate::RgbImg;
+
+const QOI_OP_INDEX: u8 = 0x00;
+const QOI_OP_DIFF: u8 = 0x40;
+const QOI_OP_LUMA: u8 = 0x80;
+const QOI_OP_RUN: u8 = 0xc0;
+const QOI_OP_RGB: u8 = 0xfe;
+const QOI_OP_RGBA: u8 = 0xff;
+const QOI_MASK_2: u8 = 0xc0;
+
+type Pixel = (u8, u8, u8, u8);
+
+fn qoi_hash(px: Pixel) -> usize {
+    let (r, g, b, a) = px;
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+/// `encode` serializes the supplied `RgbImg` into a buffer of QOI-formatted bytes.
+///
+/// Since `RgbImg` is always 8-bit RGB, the emitted stream always reports `channels = 3` and
+/// treats alpha as a constant `255` for the purposes of the running-index and diff ops.
+///
+pub fn encode(img: &RgbImg) -> Vec<u8> {
+    let total = img.total();
+    let mut out = Vec::with_capacity(14 + total * 2 + 8);
+
+    out.extend_from_slice(b"qoif");
+    out.extend_from_slice(&(img.width() as u32).to_be_bytes());
+    out.extend_from_slice(&(img.height() as u32).to_be_bytes());
+    out.push(3);
+    out.push(0);
+
+    let mut index = [(0u8, 0u8, 0u8, 0u8); 64];
+    let mut prev: Pixel = (0, 0, 0, 255);
+    let mut run = 0u8;
+
+    for (idx, (r, g, b)) in img.iter().enumerate() {
+        let px: Pixel = (r, g, b, 255);
+
+        if px == prev {
+            run += 1;
+            if run == 62 || idx + 1 == total {
+                out.push(QOI_OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+
+        if run > 0 {
+            out.push(QOI_OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let hash = qoi_hash(px);
+        if index[hash] == px {
+            out.push(QOI_OP_INDEX | hash as u8);
+        } else {
+            index[hash] = px;
+
+            let dr = px.0.wrapping_sub(prev.0) as i8;
+            let dg = px.1.wrapping_sub(prev.1) as i8;
+            let db = px.2.wrapping_sub(prev.2) as i8;
+            let dr_dg = dr.wrapping_sub(dg);
+            let db_dg = db.wrapping_sub(dg);
+
+            if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                out.push(
+                    QOI_OP_DIFF
+                        | (((dr + 2) as u8) << 4)
+                        | (((dg + 2) as u8) << 2)
+                        | ((db + 2) as u8),
+                );
+            } else if (-32..=31).contains(&dg)
+                && (-8..=7).contains(&dr_dg)
+                && (-8..=7).contains(&db_dg)
+            {
+                out.push(QOI_OP_LUMA | ((dg + 32) as u8));
+                out.push((((dr_dg + 8) as u8) << 4) | ((db_dg + 8) as u8));
+            } else {
+                out.push(QOI_OP_RGB);
+                out.push(px.0);
+                out.push(px.1);
+                out.push(px.2);
+            }
+        }
+
+        prev = px;
+    }
+
+    out.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+    out
+}
+
+/// `decode` parses a buffer of QOI-formatted bytes and reconstructs the `RgbImg` it describes.
+///
+/// The alpha channel, if present in the source image the bytes were encoded from, is discarded;
+/// `RgbImg` has no alpha channel to restore it into.
+///
+pub fn decode(buf: &[u8]) -> RgbImg {
+    let width = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]) as usize;
+    let height = u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]) as usize;
+    let total = width * height;
+
+    let mut packed = Vec::with_capacity(total * 3);
+    let mut index = [(0u8, 0u8, 0u8, 0u8); 64];
+    let mut prev: Pixel = (0, 0, 0, 255);
+
+    let mut pos = 14;
+    let mut written = 0;
+
+    while written < total {
+        let tag = buf[pos];
+
+        if tag == QOI_OP_RGB || tag == QOI_OP_RGBA {
+            let a = if tag == QOI_OP_RGBA { buf[pos + 4] } else { prev.3 };
+            prev = (buf[pos + 1], buf[pos + 2], buf[pos + 3], a);
+            pos += if tag == QOI_OP_RGBA { 1 + 4 } else { 1 + 3 };
+
+            index[qoi_hash(prev)] = prev;
+            packed.push(prev.0);
+            packed.push(prev.1);
+            packed.push(prev.2);
+            written += 1;
+            continue;
+        }
+
+        match tag & QOI_MASK_2 {
+            QOI_OP_INDEX => {
+                prev = index[(tag & 0x3f) as usize];
+                pos += 1;
+
+                packed.push(prev.0);
+                packed.push(prev.1);
+                packed.push(prev.2);
+                written += 1;
+            }
+            QOI_OP_DIFF => {
+                let dr = ((tag >> 4) & 0x3) as i8 - 2;
+                let dg = ((tag >> 2) & 0x3) as i8 - 2;
+                let db = (tag & 0x3) as i8 - 2;
+
+                prev = (
+                    prev.0.wrapping_add(dr as u8),
+                    prev.1.wrapping_add(dg as u8),
+                    prev.2.wrapping_add(db as u8),
+                    prev.3,
+                );
+                pos += 1;
+
+                index[qoi_hash(prev)] = prev;
+                packed.push(prev.0);
+                packed.push(prev.1);
+                packed.push(prev.2);
+                written += 1;
+            }
+            QOI_OP_LUMA => {
+                let dg = (tag & 0x3f) as i8 - 32;
+                let byte2 = buf[pos + 1];
+                let dr_dg = ((byte2 >> 4) & 0xf) as i8 - 8;
+                let db_dg = (byte2 & 0xf) as i8 - 8;
+
+                prev = (
+                    prev.0.wrapping_add(dg.wrapping_add(dr_dg) as u8),
+                    prev.1.wrapping_add(dg as u8),
+                    prev.2.wrapping_add(dg.wrapping_add(db_dg) as u8),
+                    prev.3,
+                );
+                pos += 2;
+
+                index[qoi_hash(prev)] = prev;
+                packed.push(prev.0);
+                packed.push(prev.1);
+                packed.push(prev.2);
+                written += 1;
+            }
+            QOI_OP_RUN => {
+                let run = (tag & 0x3f) + 1;
+                pos += 1;
+
+                for _ in 0..run {
+                    packed.push(prev.0);
+                    packed.push(prev.1);
+                    packed.push(prev.2);
+                    written += 1;
+                }
+
+                index[qoi_hash(prev)] = prev;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    RgbImg::from_packed_buf(&packed, height, width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_an_arbitrary_image() {
+        let packed: Vec<u8> = (0..(4 * 4 * 3)).map(|i| (i * 37 % 256) as u8).collect();
+        let img = RgbImg::from_packed_buf(&packed, 4, 4);
+
+        let decoded = decode(&encode(&img));
+
+        assert_eq!(decoded.r(), img.r());
+        assert_eq!(decoded.g(), img.g());
+        assert_eq!(decoded.b(), img.b());
+    }
+
+    #[test]
+    fn header_and_end_marker_match_the_qoi_spec() {
+        let img = RgbImg::from_packed_buf(&[0, 0, 0], 1, 1);
+        let encoded = encode(&img);
+
+        assert_eq!(&encoded[0..4], b"qoif");
+        assert_eq!(&encoded[4..8], &1u32.to_be_bytes()[..]);
+        assert_eq!(&encoded[8..12], &1u32.to_be_bytes()[..]);
+        assert_eq!(encoded[12], 3);
+        assert_eq!(&encoded[encoded.len() - 8..], &[0, 0, 0, 0, 0, 0, 0, 1][..]);
+    }
+
+    #[test]
+    fn round_trips_every_op_type() {
+        // (200,5,90) x4 -> literal QOI_OP_RGB, then a 3-pixel QOI_OP_RUN
+        // (201,4,90)     -> QOI_OP_DIFF off the run's pixel
+        // (221,24,110)   -> QOI_OP_LUMA off the diff pixel
+        // (200,5,90)     -> QOI_OP_INDEX, revisiting the very first pixel's cache slot
+        #[rustfmt::skip]
+        let packed: [u8; 21] = [
+            200, 5, 90, 200, 5, 90, 200, 5, 90, 200, 5, 90,
+            201, 4, 90,
+            221, 24, 110,
+            200, 5, 90,
+        ];
+        let img = RgbImg::from_packed_buf(&packed, 1, 7);
+
+        let decoded = decode(&encode(&img));
+
+        assert_eq!(decoded.r(), img.r());
+        assert_eq!(decoded.g(), img.g());
+        assert_eq!(decoded.b(), img.b());
+    }
+}